@@ -1,5 +1,5 @@
 use sn_data_types::{PublicBlob, PrivateBlob, Blob, Keypair};
-use sn_client::{Error, ErrorMessage, Client};
+use sn_client::{Error, ErrorMessage, Client, KeypairProvider, StaticProvider};
 use tokio::time::{sleep, Duration};
 use rand::prelude::Distribution;
 use rand::distributions::Standard;
@@ -11,7 +11,7 @@ use dirs_next::home_dir;
 use std::collections::HashSet;
 use std::net::SocketAddr;
 use std::path::Path;
-use std::sync::Once;
+use std::sync::{Arc, Once};
 use tracing_subscriber::{fmt, EnvFilter};
 use std::io::BufReader;
 
@@ -101,7 +101,11 @@ pub fn generate_random_vector<T>(length: usize) -> Vec<T>
 pub async fn create_test_client_with(optional_keypair: Option<Keypair>) -> Result<Client> {
     init_logger();
     let contact_info = read_network_conn_info()?;
-    let client = Client::new(optional_keypair, None, Some(contact_info)).await?;
+    // Fall back to a freshly generated keypair when none is given, same as the `Client::new`
+    // behaviour this replaces.
+    let keypair = optional_keypair.unwrap_or_else(|| Keypair::new_ed25519(&mut OsRng));
+    let provider: Arc<dyn KeypairProvider> = Arc::new(StaticProvider::from_keypair(keypair));
+    let client = Client::new_with_keypair_provider(provider, None, Some(contact_info)).await?;
     Ok(client)
 }
 
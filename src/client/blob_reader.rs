@@ -0,0 +1,220 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{Client, CoreError};
+use futures::future::{BoxFuture, FutureExt};
+use log::trace;
+use safe_nd::BlobAddress;
+use self_encryption::DataMap;
+use std::io::{self, SeekFrom};
+use std::ops::Range;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+
+/// A single buffered chunk: the logical byte range it covers, and its decrypted bytes.
+struct BufferedChunk {
+    range: Range<u64>,
+    data: Vec<u8>,
+}
+
+/// Walk a data map's chunk infos in order, turning each chunk's `source_size` into the
+/// contiguous byte range it occupies in the decrypted blob.
+fn chunk_ranges_from_data_map(data_map: &DataMap) -> Vec<Range<u64>> {
+    let mut ranges = Vec::new();
+    let mut offset = 0_u64;
+    for info in data_map.infos() {
+        let end = offset + info.source_size;
+        ranges.push(offset..end);
+        offset = end;
+    }
+    ranges
+}
+
+/// Streams an arbitrarily large self-encrypted blob without materializing the whole value,
+/// fetching only the chunk(s) that cover the current logical position.
+///
+/// `BlobReader` implements `AsyncRead` and `AsyncSeek` over `Client::read_blob`'s range path,
+/// so callers can treat a blob address like a regular seekable file and process
+/// multi-gigabyte blobs with constant memory and random access.
+pub struct BlobReader {
+    client: Client,
+    address: BlobAddress,
+    /// Byte ranges of each self-encryption chunk, in order, derived from the blob's `DataMap` -
+    /// real chunk boundaries rather than an arbitrary fixed-size window, so a fetch never asks
+    /// `read_blob` to straddle two underlying chunks.
+    chunk_ranges: Vec<Range<u64>>,
+    total_len: u64,
+    position: u64,
+    buffer: Option<BufferedChunk>,
+    pending_fetch: Option<BoxFuture<'static, Result<Vec<u8>, CoreError>>>,
+}
+
+impl BlobReader {
+    /// Create a reader over the blob already stored at `address`. `data_map` is the blob's
+    /// decrypted data map, as returned when the blob was stored - it's the source of truth for
+    /// both the blob's total decrypted length and its chunk layout, so callers don't need to
+    /// track or pass a length out of band.
+    pub fn new(client: Client, address: BlobAddress, data_map: DataMap) -> Self {
+        let chunk_ranges = chunk_ranges_from_data_map(&data_map);
+        let total_len = chunk_ranges.last().map(|range| range.end).unwrap_or(0);
+        Self {
+            client,
+            address,
+            chunk_ranges,
+            total_len,
+            position: 0,
+            buffer: None,
+            pending_fetch: None,
+        }
+    }
+
+    /// Total decrypted length of the blob being streamed.
+    pub fn len(&self) -> u64 {
+        self.total_len
+    }
+
+    /// Whether the blob is empty.
+    pub fn is_empty(&self) -> bool {
+        self.total_len == 0
+    }
+
+    /// Current logical read position.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// The self-encryption chunk range covering `offset`. Only ever called with an `offset` below
+    /// `total_len`, which the chunk ranges built from the data map always tile exactly, so this
+    /// is guaranteed to find a match.
+    fn chunk_range_for(&self, offset: u64) -> Range<u64> {
+        self.chunk_ranges
+            .iter()
+            .find(|range| range.contains(&offset))
+            .cloned()
+            .expect("offset below total_len must fall within some chunk range")
+    }
+
+    /// Whether the buffered chunk has a byte at `offset`. A position at `range.end` is NOT
+    /// covered: there is no byte there, and treating it as covered would make `poll_read` skip
+    /// fetching the next chunk and report spurious EOF.
+    fn buffer_contains(&self, offset: u64) -> bool {
+        self.buffer
+            .as_ref()
+            .map(|chunk| chunk.range.contains(&offset))
+            .unwrap_or(false)
+    }
+
+    /// Whether a seek to `offset` can keep the current buffer around. Unlike `buffer_contains`,
+    /// landing exactly on `range.end` is fine here: it's merely a hint not to eagerly drop the
+    /// buffer, since the very next read will correctly detect it doesn't cover that position.
+    fn buffer_covers_for_seek(&self, offset: u64) -> bool {
+        self.buffer
+            .as_ref()
+            .map(|chunk| chunk.range.contains(&offset) || offset == chunk.range.end)
+            .unwrap_or(false)
+            && offset < self.total_len
+    }
+}
+
+impl AsyncRead for BlobReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        if this.position >= this.total_len {
+            return Poll::Ready(Ok(()));
+        }
+
+        if !this.buffer_contains(this.position) {
+            if this.pending_fetch.is_none() {
+                let range = this.chunk_range_for(this.position);
+                trace!("BlobReader fetching chunk range {:?}", range);
+                let mut client = this.client.clone();
+                let address = this.address;
+                let offset = range.start;
+                let len = range.end - range.start;
+                this.pending_fetch = Some(
+                    async move { client.read_blob(address, Some(offset), Some(len)).await }
+                        .boxed(),
+                );
+            }
+
+            match this.pending_fetch.as_mut().expect("just set above").poll_unpin(cx) {
+                Poll::Ready(Ok(data)) => {
+                    this.pending_fetch = None;
+                    let range = this.chunk_range_for(this.position);
+                    let expected_len = (range.end - range.start) as usize;
+                    if data.len() != expected_len {
+                        return Poll::Ready(Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            format!(
+                                "expected {} bytes for chunk range {:?} but read_blob returned {}",
+                                expected_len,
+                                range,
+                                data.len()
+                            ),
+                        )));
+                    }
+                    this.buffer = Some(BufferedChunk { range, data });
+                }
+                Poll::Ready(Err(error)) => {
+                    this.pending_fetch = None;
+                    return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, error)));
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        let chunk = this.buffer.as_ref().expect("buffer populated above");
+        let chunk_offset = (this.position - chunk.range.start) as usize;
+        let available = &chunk.data[chunk_offset..];
+        let to_copy = available.len().min(buf.remaining());
+        buf.put_slice(&available[..to_copy]);
+        this.position += to_copy as u64;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncSeek for BlobReader {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+
+        let new_pos = match position {
+            SeekFrom::Start(offset) => offset as i64,
+            SeekFrom::End(offset) => this.total_len as i64 + offset,
+            SeekFrom::Current(offset) => this.position as i64 + offset,
+        };
+
+        if new_pos < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot seek to a negative position",
+            ));
+        }
+
+        // Clamp forward seeks past EOF rather than erroring, mirroring `std::io::Cursor`.
+        let new_pos = (new_pos as u64).min(this.total_len);
+
+        if !this.buffer_covers_for_seek(new_pos) {
+            // Drop any buffered chunk and in-flight fetch so a read immediately following this
+            // seek never serves stale bytes left over from the prior position.
+            this.buffer = None;
+            this.pending_fetch = None;
+        }
+        this.position = new_pos;
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.position))
+    }
+}
@@ -8,25 +8,215 @@
 
 use super::{Client, CoreError};
 use async_trait::async_trait;
+use crc32fast::Hasher as Crc32;
 use log::trace;
 use safe_nd::{Blob, BlobAddress, PrivateBlob, PublicBlob};
 use self_encryption::{Storage, StorageError};
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
+use std::sync::{Arc, Mutex};
 use xor_name::{XorName, XOR_NAME_LEN};
 
+/// Framing byte written ahead of the CRC32 + payload that is handed to `store_blob`,
+/// marking the payload as stored exactly as given.
+const FRAME_VERBATIM: u8 = 0x00;
+/// Framing byte marking the payload as zstd-compressed.
+const FRAME_ZSTD: u8 = 0x01;
+/// Number of bytes used to frame a stored payload: 1 framing byte + a 4-byte CRC32.
+const FRAME_HEADER_LEN: usize = 1 + 4;
+
+/// Frame `data` for storage when `compression_level` is enabled, as
+/// `[FRAME_VERBATIM | FRAME_ZSTD][crc32 of plaintext][payload]`; falls back to storing verbatim
+/// (still framed) if compression doesn't actually shrink the data, so incompressible chunks
+/// aren't penalised with the cost of decompression for nothing.
+///
+/// When `compression_level` is `None` (the default), `data` is returned unchanged: this keeps
+/// the on-wire chunk format and content addresses identical to chunks stored before compression
+/// support existed, and lets those pre-existing chunks still be read back without framing.
+///
+/// Shared by `BlobStorage` and `BlobStorageDryRun` so that computing a dry-run address and
+/// actually storing the chunk agree on the same bytes, as long as both are configured with the
+/// same compression level.
+fn frame_for_storage(
+    compression_level: Option<i32>,
+    data: &[u8],
+) -> Result<Vec<u8>, BlobStorageError> {
+    let level = match compression_level {
+        Some(level) => level,
+        None => return Ok(data.to_vec()),
+    };
+
+    let mut crc = Crc32::new();
+    crc.update(data);
+    let checksum = crc.finalize();
+
+    let encoded = zstd::encode_all(data, level)
+        .map_err(|e| CoreError::Unexpected(format!("Failed to zstd-compress chunk: {}", e)))?;
+
+    let mut framed = Vec::with_capacity(FRAME_HEADER_LEN + data.len());
+    if encoded.len() < data.len() {
+        framed.push(FRAME_ZSTD);
+        framed.extend_from_slice(&checksum.to_le_bytes());
+        framed.extend_from_slice(&encoded);
+    } else {
+        framed.push(FRAME_VERBATIM);
+        framed.extend_from_slice(&checksum.to_le_bytes());
+        framed.extend_from_slice(data);
+    }
+    Ok(framed)
+}
+
+/// Reverse `frame_for_storage`: a no-op when `compression_level` is `None` (matching the
+/// equally unframed bytes `frame_for_storage` would have produced), otherwise decompressing if
+/// required and verifying the CRC32 of the recovered plaintext against what was stored
+/// alongside it.
+fn unframe_from_storage(
+    compression_level: Option<i32>,
+    framed: &[u8],
+) -> Result<Vec<u8>, BlobStorageError> {
+    if compression_level.is_none() {
+        return Ok(framed.to_vec());
+    }
+
+    if framed.len() < FRAME_HEADER_LEN {
+        return Err(CoreError::Unexpected("Stored chunk is truncated.".to_owned()).into());
+    }
+
+    let frame_byte = framed[0];
+    let mut expected_crc = [0_u8; 4];
+    expected_crc.clone_from_slice(&framed[1..FRAME_HEADER_LEN]);
+    let expected_crc = u32::from_le_bytes(expected_crc);
+    let payload = &framed[FRAME_HEADER_LEN..];
+
+    let data = match frame_byte {
+        FRAME_VERBATIM => payload.to_vec(),
+        FRAME_ZSTD => zstd::decode_all(payload)
+            .map_err(|e| CoreError::Unexpected(format!("Failed to zstd-decompress chunk: {}", e)))?,
+        other => {
+            return Err(
+                CoreError::Unexpected(format!("Unknown chunk framing byte: {}", other)).into(),
+            )
+        }
+    };
+
+    let mut crc = Crc32::new();
+    crc.update(&data);
+    if crc.finalize() != expected_crc {
+        return Err(CoreError::Unexpected(
+            "CRC32 mismatch after decompressing stored chunk.".to_owned(),
+        )
+        .into());
+    }
+
+    Ok(data)
+}
+
 /// Network storage is the concrete type which self_encryption crate will use
 /// to put or get data from the network.
+///
+/// Compression is opt-in and the framing it produces is not self-describing: a chunk stored by
+/// a `BlobStorage` with `compression_level: Some(_)` is framed, and reading it back requires a
+/// `BlobStorage` constructed with a matching compression level (see `with_compression`). Pointing
+/// a plain `BlobStorage::new` instance at chunks written by a compressing one - or vice versa -
+/// produces garbage or a CRC32 mismatch, not a clean error up front; callers that share a single
+/// set of chunks must agree on one compression level throughout.
 #[derive(Clone)]
 pub struct BlobStorage {
     client: Client,
     published: bool,
+    /// `Some(level)` enables transparent zstd compression of chunk payloads at the given level.
+    compression_level: Option<i32>,
+    /// Whether `get` recomputes a fetched chunk's content address and rejects it on mismatch.
+    verify_on_get: bool,
+    /// Caches the most recently framed chunk so a `generate_address` call immediately followed
+    /// by `put` for the same bytes - self_encryption's usual call pattern - doesn't pay for zstd
+    /// compression twice. Keyed by exact byte equality rather than a checksum, so a cache hit can
+    /// never hand back the wrong framed bytes for a different chunk.
+    frame_cache: Arc<Mutex<Option<FrameCache>>>,
+}
+
+/// Remembers the input bytes a `frame_for_storage` call was last run on, alongside the result.
+struct FrameCache {
+    input: Vec<u8>,
+    framed: Vec<u8>,
 }
 
 impl BlobStorage {
-    /// Create a new BlobStorage instance.
+    /// Create a new BlobStorage instance. Compression is disabled, matching prior behaviour.
+    /// Content-address verification on `get` defaults to enabled for private blobs, since they
+    /// are more sensitive to tampering, and disabled for public ones; use `with_verify_on_get`
+    /// to override.
     pub fn new(client: Client, published: bool) -> Self {
-        Self { client, published }
+        Self::from_parts(client, published, None)
+    }
+
+    /// Create a new BlobStorage instance that transparently zstd-compresses chunk payloads
+    /// at the given level before storing them, and decompresses them on retrieval.
+    pub fn with_compression(client: Client, published: bool, level: i32) -> Self {
+        Self::from_parts(client, published, Some(level))
+    }
+
+    /// Shared by the public constructors above and `BlobStorageConfig`, which is the single
+    /// source of truth for how a `BlobStorage` gets built for a given backend selection.
+    pub(crate) fn from_parts(client: Client, published: bool, compression_level: Option<i32>) -> Self {
+        Self {
+            client,
+            published,
+            compression_level,
+            verify_on_get: !published,
+            frame_cache: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Toggle end-to-end content-address verification of chunks fetched via `get`.
+    pub fn with_verify_on_get(mut self, enabled: bool) -> Self {
+        self.verify_on_get = enabled;
+        self
+    }
+
+    /// Frame `data` for storage, reusing the last framed result if `data` is byte-for-byte the
+    /// same as the previous call. Avoids compressing the same chunk twice when self_encryption
+    /// calls `generate_address` immediately before `put` with identical bytes.
+    fn framed_bytes(&self, data: &[u8]) -> Result<Vec<u8>, BlobStorageError> {
+        if let Some(cached) = self.frame_cache.lock().expect("frame cache lock poisoned").as_ref()
+        {
+            if cached.input == data {
+                return Ok(cached.framed.clone());
+            }
+        }
+
+        let framed = frame_for_storage(self.compression_level, data)?;
+        *self.frame_cache.lock().expect("frame cache lock poisoned") = Some(FrameCache {
+            input: data.to_vec(),
+            framed: framed.clone(),
+        });
+        Ok(framed)
+    }
+
+    /// Recompute the content address of a just-fetched chunk's stored bytes the same way
+    /// `generate_address` would, and compare it to the address that was actually requested.
+    /// Guards against handing corrupt or tampered bytes to `self_encryption`.
+    async fn verify_content_address(
+        &self,
+        requested: &XorName,
+        stored_bytes: &[u8],
+    ) -> Result<(), BlobStorageError> {
+        let blob: Blob = if self.published {
+            PublicBlob::new(stored_bytes.to_vec()).into()
+        } else {
+            PrivateBlob::new(stored_bytes.to_vec(), self.client.public_key().await).into()
+        };
+
+        if &blob.name() != requested {
+            return Err(CoreError::Unexpected(
+                "Retrieved chunk's content address does not match the address requested; data \
+                 may be corrupt or tampered with."
+                    .to_owned(),
+            )
+            .into());
+        }
+
+        Ok(())
     }
 }
 
@@ -56,17 +246,23 @@ impl Storage for BlobStorage {
         };
 
         match self.client.get_blob(address, None, None).await {
-            Ok(data) => Ok(data.value().clone()),
+            Ok(data) => {
+                if self.verify_on_get {
+                    self.verify_content_address(&name, data.value()).await?;
+                }
+                unframe_from_storage(self.compression_level, data.value())
+            }
             Err(error) => Err(BlobStorageError::from(error)),
         }
     }
 
     async fn put(&mut self, _: Vec<u8>, data: Vec<u8>) -> Result<(), Self::Error> {
         trace!("Self encrypt invoked PutBlob.");
+        let framed = self.framed_bytes(&data)?;
         let blob: Blob = if self.published {
-            PublicBlob::new(data).into()
+            PublicBlob::new(framed).into()
         } else {
-            PrivateBlob::new(data, self.client.public_key().await).into()
+            PrivateBlob::new(framed, self.client.public_key().await).into()
         };
         match self.client.store_blob(blob).await {
             Ok(_r) => Ok(()),
@@ -74,11 +270,21 @@ impl Storage for BlobStorage {
         }
     }
 
+    // self_encryption calls this once per chunk to compute its address and, typically right
+    // after, `put` to actually store it. `framed_bytes` caches the last framed result by exact
+    // byte equality so that common pairing doesn't pay for zstd compression twice; a call with
+    // different bytes (or out of that order) still frames correctly, just without the reuse.
     async fn generate_address(&self, data: &[u8]) -> Vec<u8> {
+        // Must hash the exact bytes `put` will store, or the self_encryption-computed address
+        // would no longer match what ends up on the network. `put` fails outright on a framing
+        // error, so fail the same way here rather than silently hashing different bytes.
+        let framed = self
+            .framed_bytes(data)
+            .expect("failed to frame chunk for address generation; `put` would fail identically");
         let blob: Blob = if self.published {
-            PublicBlob::new(data.to_vec()).into()
+            PublicBlob::new(framed).into()
         } else {
-            PrivateBlob::new(data.to_vec(), self.client.public_key().await).into()
+            PrivateBlob::new(framed, self.client.public_key().await).into()
         };
         blob.name().0.to_vec()
     }
@@ -114,12 +320,31 @@ impl StorageError for BlobStorageError {}
 pub struct BlobStorageDryRun {
     client: Client,
     published: bool,
+    /// Must match the compression level the `BlobStorage` actually storing the chunks is
+    /// constructed with, so the addresses this dry run generates agree with what gets stored.
+    compression_level: Option<i32>,
 }
 
 impl BlobStorageDryRun {
-    /// Create a new BlobStorage instance.
+    /// Create a new BlobStorageDryRun instance. Compression is disabled, matching prior behaviour.
     pub fn new(client: Client, published: bool) -> Self {
-        Self { client, published }
+        Self::from_parts(client, published, None)
+    }
+
+    /// Create a new BlobStorageDryRun instance that generates addresses as if chunks were
+    /// zstd-compressed at the given level, matching a `BlobStorage` constructed the same way.
+    pub fn with_compression(client: Client, published: bool, level: i32) -> Self {
+        Self::from_parts(client, published, Some(level))
+    }
+
+    /// Shared by the public constructors above and `BlobStorageConfig`, which is the single
+    /// source of truth for how a `BlobStorageDryRun` gets built for a given backend selection.
+    pub(crate) fn from_parts(client: Client, published: bool, compression_level: Option<i32>) -> Self {
+        Self {
+            client,
+            published,
+            compression_level,
+        }
     }
 }
 
@@ -142,11 +367,57 @@ impl Storage for BlobStorageDryRun {
     }
 
     async fn generate_address(&self, data: &[u8]) -> Vec<u8> {
+        let framed = frame_for_storage(self.compression_level, data)
+            .expect("failed to frame chunk for address generation");
         let blob: Blob = if self.published {
-            PublicBlob::new(data.to_vec()).into()
+            PublicBlob::new(framed).into()
         } else {
-            PrivateBlob::new(data.to_vec(), self.client.public_key().await).into()
+            PrivateBlob::new(framed, self.client.public_key().await).into()
         };
         blob.name().0.to_vec()
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_for_storage_is_passthrough_when_compression_disabled() {
+        let data = b"some chunk bytes".to_vec();
+        let framed = frame_for_storage(None, &data).unwrap();
+        assert_eq!(framed, data);
+        assert_eq!(unframe_from_storage(None, &framed).unwrap(), data);
+    }
+
+    #[test]
+    fn frame_for_storage_round_trips_compressible_data() {
+        // Long run of repeated bytes: zstd will shrink this well below its original size.
+        let data = vec![7_u8; 4096];
+        let framed = frame_for_storage(Some(3), &data).unwrap();
+        assert_eq!(framed[0], FRAME_ZSTD);
+        assert!(framed.len() < data.len());
+        assert_eq!(unframe_from_storage(Some(3), &framed).unwrap(), data);
+    }
+
+    #[test]
+    fn frame_for_storage_falls_back_to_verbatim_for_incompressible_data() {
+        // A handful of bytes in non-repeating order: zstd's encoded form plus its own framing
+        // overhead won't beat the original, so `frame_for_storage` should store it verbatim.
+        let data = vec![0_u8, 1, 2, 3, 255, 254, 253, 252, 17, 201, 88, 6];
+        let framed = frame_for_storage(Some(19), &data).unwrap();
+        assert_eq!(framed[0], FRAME_VERBATIM);
+        assert_eq!(unframe_from_storage(Some(19), &framed).unwrap(), data);
+    }
+
+    #[test]
+    fn unframe_from_storage_rejects_crc_mismatch() {
+        let data = vec![42_u8; 4096];
+        let mut framed = frame_for_storage(Some(3), &data).unwrap();
+        // Flip a payload byte without touching the stored CRC32, simulating corruption or
+        // tampering in transit.
+        let last = framed.len() - 1;
+        framed[last] ^= 0xFF;
+        assert!(unframe_from_storage(Some(3), &framed).is_err());
+    }
 }
\ No newline at end of file
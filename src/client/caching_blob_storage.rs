@@ -0,0 +1,203 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{BlobStorageError, CoreError};
+use async_trait::async_trait;
+use log::trace;
+use lru::LruCache;
+use safe_nd::BlobAddress;
+use self_encryption::Storage;
+use std::sync::{Arc, Mutex};
+use xor_name::{XorName, XOR_NAME_LEN};
+
+/// Hit/miss counters for a `CachingBlobStorage`, as returned by `cache_stats()`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+struct CacheInner {
+    capacity_bytes: u64,
+    used_bytes: u64,
+    /// `LruCache` tracks recency internally with O(1) updates on both `get` and `put`; it's left
+    /// unbounded by entry count since eviction here is driven by `used_bytes` against
+    /// `capacity_bytes` instead, via `pop_lru`.
+    entries: LruCache<BlobAddress, Vec<u8>>,
+    stats: CacheStats,
+}
+
+impl CacheInner {
+    fn new(capacity_bytes: u64) -> Self {
+        Self {
+            capacity_bytes,
+            used_bytes: 0,
+            entries: LruCache::unbounded(),
+            stats: CacheStats::default(),
+        }
+    }
+
+    fn get(&mut self, address: &BlobAddress) -> Option<Vec<u8>> {
+        let found = self.entries.get(address).cloned();
+        if found.is_some() {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+        }
+        found
+    }
+
+    fn insert(&mut self, address: BlobAddress, data: Vec<u8>) {
+        // Don't evict the whole cache to fit a single chunk larger than its capacity.
+        if data.len() as u64 > self.capacity_bytes {
+            return;
+        }
+
+        if let Some(old) = self.entries.put(address, data.clone()) {
+            self.used_bytes -= old.len() as u64;
+        }
+        self.used_bytes += data.len() as u64;
+
+        while self.used_bytes > self.capacity_bytes {
+            match self.entries.pop_lru() {
+                Some((_, evicted)) => self.used_bytes -= evicted.len() as u64,
+                None => break,
+            }
+        }
+    }
+}
+
+/// Wraps an inner `Storage` with an optional LRU cache keyed by `BlobAddress`, bounded by total
+/// bytes rather than entry count so large chunks can't blow past the configured memory budget.
+///
+/// `get` checks the cache first and inserts on miss; `put` populates the cache with what it just
+/// stored. This avoids repeated network round-trips for chunks that are re-requested, e.g. when
+/// several blobs share self-encryption chunk boundaries. `BlobReader` fetches through
+/// `Client::read_blob` rather than a `Storage`, so its reads do not go through this cache.
+pub struct CachingBlobStorage {
+    inner: Box<dyn Storage<Error = BlobStorageError> + Send + Sync>,
+    published: bool,
+    cache: Arc<Mutex<CacheInner>>,
+}
+
+impl CachingBlobStorage {
+    /// Wrap `inner`, caching up to `capacity_bytes` worth of chunks.
+    pub fn new(
+        inner: Box<dyn Storage<Error = BlobStorageError> + Send + Sync>,
+        published: bool,
+        capacity_bytes: u64,
+    ) -> Self {
+        Self {
+            inner,
+            published,
+            cache: Arc::new(Mutex::new(CacheInner::new(capacity_bytes))),
+        }
+    }
+
+    /// Current cache hit/miss counts.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.lock().expect("cache lock poisoned").stats
+    }
+
+    fn address_for(&self, name: &[u8]) -> Result<BlobAddress, BlobStorageError> {
+        if name.len() != XOR_NAME_LEN {
+            return Err(CoreError::Unexpected("Requested `name` is incorrect size.".to_owned()).into());
+        }
+        let mut temp = [0_u8; XOR_NAME_LEN];
+        temp.clone_from_slice(name);
+        let name = XorName(temp);
+        Ok(if self.published {
+            BlobAddress::Public(name)
+        } else {
+            BlobAddress::Private(name)
+        })
+    }
+}
+
+#[async_trait]
+impl Storage for CachingBlobStorage {
+    type Error = BlobStorageError;
+
+    async fn get(&mut self, name: &[u8]) -> Result<Vec<u8>, Self::Error> {
+        let address = self.address_for(name)?;
+
+        if let Some(data) = self.cache.lock().expect("cache lock poisoned").get(&address) {
+            trace!("CachingBlobStorage cache hit for {:?}", address);
+            return Ok(data);
+        }
+
+        let data = self.inner.get(name).await?;
+        self.cache
+            .lock()
+            .expect("cache lock poisoned")
+            .insert(address, data.clone());
+        Ok(data)
+    }
+
+    async fn put(&mut self, name: Vec<u8>, data: Vec<u8>) -> Result<(), Self::Error> {
+        self.inner.put(name.clone(), data.clone()).await?;
+
+        if let Ok(address) = self.address_for(&self.inner.generate_address(&data).await) {
+            self.cache
+                .lock()
+                .expect("cache lock poisoned")
+                .insert(address, data);
+        }
+        Ok(())
+    }
+
+    async fn generate_address(&self, data: &[u8]) -> Vec<u8> {
+        self.inner.generate_address(data).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(byte: u8) -> BlobAddress {
+        BlobAddress::Public(XorName([byte; XOR_NAME_LEN]))
+    }
+
+    #[test]
+    fn records_hits_and_misses() {
+        let mut cache = CacheInner::new(1024);
+        assert!(cache.get(&address(1)).is_none());
+
+        cache.insert(address(1), vec![1, 2, 3]);
+        assert_eq!(cache.get(&address(1)), Some(vec![1, 2, 3]));
+
+        assert_eq!(cache.stats, CacheStats { hits: 1, misses: 1 });
+    }
+
+    #[test]
+    fn evicts_least_recently_used_entry_once_over_byte_capacity() {
+        let mut cache = CacheInner::new(10);
+        cache.insert(address(1), vec![0_u8; 4]);
+        cache.insert(address(2), vec![0_u8; 4]);
+
+        // Touch address 1 so address 2 becomes the least-recently-used entry.
+        assert!(cache.get(&address(1)).is_some());
+
+        // Pushes used_bytes to 12, over the 10 byte capacity; address 2 should be evicted first.
+        cache.insert(address(3), vec![0_u8; 4]);
+
+        assert!(cache.get(&address(1)).is_some());
+        assert!(cache.get(&address(2)).is_none());
+        assert!(cache.get(&address(3)).is_some());
+        assert!(cache.used_bytes <= 10);
+    }
+
+    #[test]
+    fn refuses_to_cache_a_chunk_larger_than_capacity() {
+        let mut cache = CacheInner::new(4);
+        cache.insert(address(1), vec![0_u8; 8]);
+        assert!(cache.get(&address(1)).is_none());
+        assert_eq!(cache.used_bytes, 0);
+    }
+}
@@ -0,0 +1,108 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{BlobStorageError, CoreError};
+use async_trait::async_trait;
+use log::trace;
+use safe_nd::{Blob, PrivateBlob, PublicBlob, PublicKey};
+use self_encryption::Storage;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use xor_name::{XorName, XOR_NAME_LEN};
+
+/// A `Storage` backend that keeps chunks purely in-process behind a shared lock, instead of
+/// putting or getting them from the network. Useful for unit tests and tooling that drive a
+/// `self_encryption::Storage` directly without a live node. `Client` does not use this backend,
+/// so flows that go through `Client` (such as `gen_data_then_create_and_retrieve`) still need a
+/// live node.
+#[derive(Clone)]
+pub struct InMemoryBlobStorage {
+    published: bool,
+    owner: Option<PublicKey>,
+    chunks: Arc<Mutex<HashMap<XorName, Vec<u8>>>>,
+}
+
+impl InMemoryBlobStorage {
+    /// Create a new empty in-memory backend. `owner` is required when `published` is `false`,
+    /// since private blob addresses are derived from their owner's public key.
+    pub fn new(published: bool, owner: Option<PublicKey>) -> Self {
+        Self {
+            published,
+            owner,
+            chunks: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn make_blob(&self, data: Vec<u8>) -> Blob {
+        if self.published {
+            PublicBlob::new(data).into()
+        } else {
+            let owner = self
+                .owner
+                .expect("InMemoryBlobStorage configured for private blobs requires an owner key");
+            PrivateBlob::new(data, owner).into()
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for InMemoryBlobStorage {
+    type Error = BlobStorageError;
+
+    async fn get(&mut self, name: &[u8]) -> Result<Vec<u8>, Self::Error> {
+        trace!("Self encrypt invoked GetBlob on in-memory storage.");
+
+        if name.len() != XOR_NAME_LEN {
+            return Err(CoreError::Unexpected("Requested `name` is incorrect size.".to_owned()).into());
+        }
+        let mut temp = [0_u8; XOR_NAME_LEN];
+        temp.clone_from_slice(name);
+        let name = XorName(temp);
+
+        let chunks = self.chunks.lock().await;
+        chunks.get(&name).cloned().ok_or_else(|| {
+            CoreError::Unexpected("Chunk not found in in-memory storage.".to_owned()).into()
+        })
+    }
+
+    async fn put(&mut self, _: Vec<u8>, data: Vec<u8>) -> Result<(), Self::Error> {
+        trace!("Self encrypt invoked PutBlob on in-memory storage.");
+        let blob = self.make_blob(data.clone());
+        self.chunks.lock().await.insert(blob.name(), data);
+        Ok(())
+    }
+
+    async fn generate_address(&self, data: &[u8]) -> Vec<u8> {
+        self.make_blob(data.to_vec()).name().0.to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn put_then_get_round_trips_a_published_chunk() {
+        let mut storage = InMemoryBlobStorage::new(true, None);
+        let data = b"chunk bytes".to_vec();
+
+        let name = storage.generate_address(&data).await;
+        storage.put(name.clone(), data.clone()).await.expect("put failed");
+
+        assert_eq!(storage.get(&name).await.expect("get failed"), data);
+    }
+
+    #[tokio::test]
+    async fn get_of_an_unknown_address_errors() {
+        let mut storage = InMemoryBlobStorage::new(true, None);
+        let name = storage.generate_address(b"never stored").await;
+
+        assert!(storage.get(&name).await.is_err());
+    }
+}
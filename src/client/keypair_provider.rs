@@ -0,0 +1,125 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::CoreError;
+use async_trait::async_trait;
+use rand::rngs::OsRng;
+use safe_nd::Keypair;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+/// Supplies the `Keypair` a `Client` authenticates as, decoupling identity management from
+/// `Client` construction. Implementations can load credentials from disk, generate and persist
+/// one on first use, or reach out to an external secret store, letting applications manage
+/// multiple identities and rotate keys without threading raw `Keypair` values through every call
+/// site that constructs a `Client`.
+///
+/// See `Client::new_with_keypair_provider`, which calls `keypair()` to obtain the `Keypair` it
+/// connects with instead of taking one directly.
+#[async_trait]
+pub trait KeypairProvider: Send + Sync {
+    /// Obtain the keypair a `Client` should connect with.
+    async fn keypair(&self) -> Result<Keypair, CoreError>;
+}
+
+/// A `KeypairProvider` that always yields the same keypair, either given directly as a literal
+/// or loaded from (and lazily generated into) a file.
+pub struct StaticProvider {
+    keypair: Keypair,
+}
+
+impl StaticProvider {
+    /// Serve `keypair` as-is.
+    pub fn from_keypair(keypair: Keypair) -> Self {
+        Self { keypair }
+    }
+
+    /// Load a keypair serialised at `path`, generating and persisting a fresh one if the file
+    /// doesn't exist yet.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CoreError> {
+        let path = path.as_ref();
+
+        let keypair = if path.exists() {
+            let file = File::open(path).map_err(|e| {
+                CoreError::Unexpected(format!(
+                    "Failed to open keypair file '{}': {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+            serde_json::from_reader(BufReader::new(file)).map_err(|e| {
+                CoreError::Unexpected(format!(
+                    "Failed to parse keypair file '{}': {}",
+                    path.display(),
+                    e
+                ))
+            })?
+        } else {
+            let keypair = Keypair::new_ed25519(&mut OsRng);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| {
+                    CoreError::Unexpected(format!(
+                        "Failed to create keypair directory '{}': {}",
+                        parent.display(),
+                        e
+                    ))
+                })?;
+            }
+            let serialised = serde_json::to_vec(&keypair).map_err(|e| {
+                CoreError::Unexpected(format!("Failed to serialise generated keypair: {}", e))
+            })?;
+            fs::write(path, serialised).map_err(|e| {
+                CoreError::Unexpected(format!(
+                    "Failed to persist generated keypair to '{}': {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+            keypair
+        };
+
+        Ok(Self { keypair })
+    }
+}
+
+#[async_trait]
+impl KeypairProvider for StaticProvider {
+    async fn keypair(&self) -> Result<Keypair, CoreError> {
+        Ok(self.keypair.clone())
+    }
+}
+
+/// A `KeypairProvider` that resolves credentials per-user from a configured directory, one
+/// keypair file per user id, analogous to how `read_network_conn_info` locates a fixed
+/// `.safe/node/...` path for node connection info.
+pub struct DirectoryKeypairProvider {
+    root: PathBuf,
+    user_id: String,
+}
+
+impl DirectoryKeypairProvider {
+    /// `root` holds one keypair file per user, named `<user_id>.keypair`.
+    pub fn new(root: impl Into<PathBuf>, user_id: impl Into<String>) -> Self {
+        Self {
+            root: root.into(),
+            user_id: user_id.into(),
+        }
+    }
+
+    fn keypair_path(&self) -> PathBuf {
+        self.root.join(format!("{}.keypair", self.user_id))
+    }
+}
+
+#[async_trait]
+impl KeypairProvider for DirectoryKeypairProvider {
+    async fn keypair(&self) -> Result<Keypair, CoreError> {
+        StaticProvider::from_file(self.keypair_path())?.keypair().await
+    }
+}
@@ -0,0 +1,44 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{Client, CoreError};
+use safe_nd::Keypair;
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+mod blob_reader;
+mod blob_storage;
+mod blob_storage_config;
+mod caching_blob_storage;
+mod in_memory_blob_storage;
+mod keypair_provider;
+
+pub use blob_reader::BlobReader;
+pub use blob_storage::{BlobStorage, BlobStorageDryRun, BlobStorageError};
+pub use blob_storage_config::BlobStorageConfig;
+pub use caching_blob_storage::{CacheStats, CachingBlobStorage};
+pub use in_memory_blob_storage::InMemoryBlobStorage;
+pub use keypair_provider::{DirectoryKeypairProvider, KeypairProvider, StaticProvider};
+
+impl Client {
+    /// Construct a `Client`, obtaining its keypair from `provider` instead of requiring a raw
+    /// `Keypair` up front. `provider` is free to lazily generate and persist a keypair on first
+    /// use, letting applications manage multiple identities, rotate keys, and back credentials
+    /// with an external secret store instead of threading a `Keypair` through every call site
+    /// that constructs a `Client`.
+    pub async fn new_with_keypair_provider(
+        provider: Arc<dyn KeypairProvider>,
+        config_root: Option<PathBuf>,
+        bootstrap_config: Option<HashSet<SocketAddr>>,
+    ) -> Result<Self, CoreError> {
+        let keypair: Keypair = provider.keypair().await?;
+        Self::new(Some(keypair), config_root, bootstrap_config).await
+    }
+}